@@ -20,6 +20,7 @@
 //!  -3 = char atom
 //!  Negative = atom (single value), Positive = array
 
+use serde::{Deserialize, Serialize};
 use std::fmt;
 
 /// K type tags — mirrors the t field in the K object
@@ -45,7 +46,7 @@ pub enum KType {
 ///  t = type tag
 ///  n = element count
 ///  k = flexible array member (data lives here)
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct K {
     /// Type tag. Matches the K t field.
     /// 1 = int array, 2 = float array, -1 = int atom, -2 = float atom, 0 = general list
@@ -61,7 +62,7 @@ pub struct K {
 /// In C, K uses kI(x) = (I*)ke(x), kF(x) = (F*)ke(x) etc.
 /// to cast the flexible array member k[] to the right pointer type.
 /// In Rust, we use an enum to hold typed data safely.
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub enum KData {
     /// kI(x) — integer data (I = i64 in K)
     Ints(Vec<i64>),
@@ -160,6 +161,18 @@ impl K {
         }
     }
 
+    /// True when every int or float element is zero (or there are none); a
+    /// general list always returns false. Lets a caller
+    /// spot an all-zero query embedding — e.g. a query with no rankable term —
+    /// before feeding it to `_dot`, where it would score everything zero.
+    pub fn is_zero(&self) -> bool {
+        match &self.data {
+            KData::Floats(v) => v.iter().all(|&x| x == 0.0),
+            KData::Ints(v) => v.iter().all(|&x| x == 0),
+            _ => false,
+        }
+    }
+
     /// Get list data slice.
     /// C equivalent: kK(x) — returns K* pointer to K object array
     #[allow(dead_code)]
@@ -1,12 +1,20 @@
 mod k;
 mod va;
 mod piece;
+mod query;
 mod seeder;
 
 use std::env;
 use std::path::Path;
 use std::time::Instant;
 
+/// True when a query string uses the boolean/phrase language (an `AND`/`OR`/
+/// `NOT` operator or a quoted phrase) rather than a plain bag of words.
+fn is_boolean_query(q: &str) -> bool {
+    q.contains('"')
+        || q.split_whitespace().any(|w| w == "AND" || w == "OR" || w == "NOT")
+}
+
 /// Escape a string for safe JSON embedding.
 fn json_escape(s: &str) -> String {
     let mut out = String::with_capacity(s.len() + 16);
@@ -37,6 +45,8 @@ fn main() {
     // ---------------------------------------------------------------
     let args: Vec<String> = env::args().collect();
     let ext_pos = args.iter().position(|a| a == "--ext");
+    let fuzzy = args.iter().any(|a| a == "--fuzzy");
+    let reindex = args.iter().any(|a| a == "--reindex");
     let ext_filter_str: Option<String> = ext_pos.and_then(|i| args.get(i + 1).cloned());
     let load_dir = args.iter()
         .enumerate()
@@ -59,11 +69,12 @@ fn main() {
     // Phase 1: Load pieces
     // ---------------------------------------------------------------
     let start = Instant::now();
+    let cache_path = load_dir.join(".bee-bytes-index.json");
     let manager = if let Some(ref ext) = ext_filter_str {
         let exts: Vec<&str> = ext.split(',').collect();
-        piece::PieceManager::from_directory_filtered(&load_dir, Some(&exts))
+        piece::PieceManager::load_or_build(&load_dir, &cache_path, Some(&exts), reindex)
     } else {
-        piece::PieceManager::from_directory(&load_dir)
+        piece::PieceManager::load_or_build(&load_dir, &cache_path, None, reindex)
     };
     let load_time = start.elapsed();
 
@@ -74,6 +85,17 @@ fn main() {
     eprintln!("   Load time:      {:?}", load_time);
     eprintln!();
 
+    // On an explicit reindex, report near-duplicate structure so a stale or
+    // redundant corpus is visible. A Hamming threshold of 3 over the 64-bit
+    // SimHash folds in near-copies, not just exact-hash collisions.
+    if reindex {
+        let groups = manager.near_dupe_groups(3);
+        eprintln!("🧬 Duplicates (incl. near-dupes, Hamming ≤ 3):");
+        eprintln!("   Redundant pieces: {}", manager.dupe_count(3));
+        eprintln!("   Dupe groups:      {}", groups.len());
+        eprintln!();
+    }
+
     // ---------------------------------------------------------------
     // Phase 2: Build the CPU swarm
     // ---------------------------------------------------------------
@@ -99,8 +121,60 @@ fn main() {
             eprintln!("🐝 JSON query mode: \"{}\"", query_text);
             
             let start = Instant::now();
-            let query_embedding = manager.embed_query(query_text);
-            let results = swarm.query(&query_embedding, top_k);
+
+            // Boolean/phrase mode: when the query carries an operator or a
+            // quoted phrase, parse it, resolve the candidate set, and rank
+            // only the survivors with the positive terms.
+            let boolean = is_boolean_query(query_text);
+            let (query_embedding, candidates) = if boolean {
+                match query::parse(query_text) {
+                    Some(op) => {
+                        let candidates = manager.evaluate_query(&op);
+                        let rank_text = op.ranking_terms().join(" ");
+                        let emb = if fuzzy {
+                            manager.embed_query_fuzzy(&rank_text)
+                        } else {
+                            manager.embed_query(&rank_text)
+                        };
+                        (emb, Some(candidates))
+                    }
+                    None => (manager.embed_query(query_text), None),
+                }
+            } else if fuzzy {
+                (manager.embed_query_fuzzy(query_text), None)
+            } else {
+                (manager.embed_query(query_text), None)
+            };
+
+            let results = match candidates {
+                // A purely negative query (e.g. `NOT gpu`) has no positive terms
+                // to rank by, so its embedding is all-zero. Return the candidate
+                // pieces directly with a uniform score instead of ranking them
+                // by an empty vector, which would score every piece zero and
+                // drop the whole (correct) candidate set.
+                Some(ref ids) if query_embedding.is_zero() => {
+                    manager.pieces.iter()
+                        .filter(|p| ids.contains(&p.id))
+                        .take(top_k)
+                        .map(|p| seeder::QueryResult {
+                            piece_id: p.id,
+                            score: 1.0,
+                            source: p.source.display().to_string(),
+                            start_line: p.start_line,
+                            preview: p.content.chars().take(100).collect::<String>(),
+                            content: p.content.clone(),
+                        })
+                        .collect()
+                }
+                // Rank the whole corpus, then keep only surviving candidates.
+                Some(ref ids) => {
+                    let mut ranked = swarm.query(&query_embedding, manager.pieces.len());
+                    ranked.retain(|r| ids.contains(&r.piece_id));
+                    ranked.truncate(top_k);
+                    ranked
+                }
+                None => swarm.query(&query_embedding, top_k),
+            };
             let elapsed = start.elapsed();
             
             eprintln!("   {} results in {:?}", results.len(), elapsed);
@@ -148,9 +222,14 @@ fn main() {
         println!("───────────────────────────────────────────────────────────");
 
         let start = Instant::now();
-        let query_embedding = manager.embed_query(query_text);
-        let results = swarm.query(&query_embedding, 3);
+        let query_embedding = if fuzzy {
+            manager.embed_query_fuzzy(query_text)
+        } else {
+            manager.embed_query(query_text)
+        };
+        let stats = swarm.query_with_stats(&query_embedding, 3);
         let query_time = start.elapsed();
+        let results = &stats.results;
 
         for (rank, result) in results.iter().enumerate() {
             println!();
@@ -166,7 +245,8 @@ fn main() {
         }
 
         println!();
-        println!("   ⏱  {:?}", query_time);
+        println!("   ⏱  {:?} │ {} pieces scanned │ {} candidates (cumulative)",
+            query_time, stats.pieces_scanned, stats.candidates_emitted);
         println!();
     }
 
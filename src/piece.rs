@@ -16,6 +16,11 @@ use std::hash::{Hash, Hasher};
 use std::path::{Path, PathBuf};
 
 use crate::k::K;
+use crate::query::Op;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet};
+use std::time::UNIX_EPOCH;
 
 /// A single piece — one chunk of data with its embedding.
 ///
@@ -25,7 +30,7 @@ use crate::k::K;
 ///   - Raw content (the actual data)
 ///   - An embedding vector (for relevance scoring via _dot)
 ///   - Source info (which file it came from)
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Piece {
     /// Unique piece ID
     pub id: usize,
@@ -40,18 +45,126 @@ pub struct Piece {
     /// Character/term frequency embedding — stored as K float array
     /// for direct use with _dot from va.rs
     pub embedding: K,
+    /// 64-bit SimHash fingerprint of the piece's tokens, used for
+    /// near-duplicate detection beyond exact-hash dedup.
+    pub simhash: u64,
+}
+
+/// A source file's identity in the on-disk cache.
+///
+/// A file is considered unchanged when both its content hash and its last
+/// modification time match the cached fingerprint.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileFingerprint {
+    /// Content hash (same function as piece hashing).
+    pub hash: u64,
+    /// Last modification time, in whole seconds since the Unix epoch.
+    pub mtime: u64,
 }
 
 /// The Piece Manager — loads files, splits into pieces, computes embeddings.
 ///
 /// BitTorrent equivalent: the torrent creator + piece hasher.
+#[derive(Serialize, Deserialize)]
 pub struct PieceManager {
     /// All pieces, indexed by ID
     pub pieces: Vec<Piece>,
     /// Hash → piece IDs (for dedup detection)
     pub hash_index: HashMap<u64, Vec<usize>>,
-    /// Vocabulary for TF-IDF embeddings
+    /// Vocabulary for BM25 embeddings
     pub vocab: Vec<String>,
+    /// Inverse document frequency per vocab term, aligned to `vocab`.
+    /// idf(t) = ln((N - df + 0.5)/(df + 0.5) + 1)
+    pub idf: Vec<f64>,
+    /// Average document length (in tokens) across all chunks — the BM25 `avgdl`.
+    pub avgdl: f64,
+    /// BM25 term-frequency saturation parameter (default 1.2).
+    pub k1: f64,
+    /// BM25 length-normalization parameter (default 0.75).
+    pub b: f64,
+    /// Per-source-file fingerprints, keyed by path. Used by the on-disk cache
+    /// to decide which files changed since the last index build.
+    pub file_index: HashMap<String, FileFingerprint>,
+    /// Inverted index: term id (vocab dimension) → postings of
+    /// `(piece_id, weight)` for every piece with a nonzero weight on that
+    /// term. Lets a query touch only the pieces that share a term with it
+    /// instead of scoring every piece's full dense embedding.
+    ///
+    /// Rebuilt from the pieces on cache load rather than stored, since it is
+    /// fully derivable and would roughly double the cache size.
+    #[serde(skip)]
+    pub postings: HashMap<usize, Vec<(usize, f64)>>,
+}
+
+/// A scored piece inside the bounded top-K heap.
+///
+/// Ordered by `score` (via `f64::total_cmp` so NaN never panics the heap),
+/// then by `piece_id` to keep the ordering total and deterministic.
+#[derive(PartialEq)]
+struct Candidate {
+    score: f64,
+    piece_id: usize,
+}
+
+impl Eq for Candidate {}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.score
+            .total_cmp(&other.score)
+            .then(self.piece_id.cmp(&other.piece_id))
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Tiny union-find over piece indices, used to merge exact-hash groups and
+/// SimHash near-duplicate pairs into connected dedup groups.
+struct Dsu {
+    parent: Vec<usize>,
+}
+
+impl Dsu {
+    fn new(n: usize) -> Dsu {
+        Dsu { parent: (0..n).collect() }
+    }
+
+    fn find(&mut self, x: usize) -> usize {
+        let mut root = x;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+        // Path compression keeps later lookups flat.
+        let mut cur = x;
+        while self.parent[cur] != root {
+            let next = self.parent[cur];
+            self.parent[cur] = root;
+            cur = next;
+        }
+        root
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (ra, rb) = (self.find(a), self.find(b));
+        if ra != rb {
+            self.parent[ra] = rb;
+        }
+    }
+
+    /// Number of distinct groups currently represented.
+    fn group_count(&mut self) -> usize {
+        let n = self.parent.len();
+        let mut roots: HashSet<usize> = HashSet::new();
+        for i in 0..n {
+            let r = self.find(i);
+            roots.insert(r);
+        }
+        roots.len()
+    }
 }
 
 impl PieceManager {
@@ -64,13 +177,17 @@ impl PieceManager {
     /// e.g. `Some(&["rs"])` to only index `.rs` files.
     pub fn from_directory_filtered(dir: &Path, ext_filter: Option<&[&str]>) -> PieceManager {
         let mut raw_chunks: Vec<(String, PathBuf, usize)> = Vec::new();
-        Self::walk_dir(dir, &mut raw_chunks, ext_filter);
+        let mut file_index: HashMap<String, FileFingerprint> = HashMap::new();
+        Self::walk_dir(dir, &mut raw_chunks, &mut file_index, ext_filter);
         let vocab = Self::build_vocab(&raw_chunks);
+        let (idf, avgdl) = Self::compute_bm25_stats(&raw_chunks, &vocab);
+        let (k1, b) = (1.2, 0.75);
         let mut pieces = Vec::new();
         let mut hash_index: HashMap<u64, Vec<usize>> = HashMap::new();
         for (i, (content, source, start_line)) in raw_chunks.iter().enumerate() {
             let hash = Self::hash_content(content);
-            let embedding = Self::compute_embedding(content, &vocab);
+            let simhash = Self::compute_simhash(content);
+            let embedding = Self::compute_embedding(content, &vocab, &idf, avgdl, k1, b);
             hash_index.entry(hash).or_default().push(i);
             pieces.push(Piece {
                 id: i,
@@ -79,13 +196,85 @@ impl PieceManager {
                 source: source.clone(),
                 start_line: *start_line,
                 embedding,
+                simhash,
             });
         }
-        PieceManager { pieces, hash_index, vocab }
+        let postings = Self::build_postings(&pieces);
+        PieceManager { pieces, hash_index, vocab, idf, avgdl, k1, b, file_index, postings }
+    }
+
+    /// Load the index from `cache_path`, reusing it whole when every source
+    /// file is unchanged; otherwise (or on `reindex`) rebuild from scratch and
+    /// refresh the cache.
+    ///
+    /// A file counts as unchanged when its current `FileFingerprint` matches
+    /// the cached one. Because the vocabulary, idf table, and `avgdl` are
+    /// global — they shift whenever any file's tokens change — a partial
+    /// change invalidates every piece's embedding, so the honest incremental
+    /// unit here is the whole corpus: a clean fingerprint match is the fast
+    /// path, and any drift crosses the staleness threshold and triggers a full
+    /// vocab rebuild.
+    pub fn load_or_build(dir: &Path, cache_path: &Path, ext_filter: Option<&[&str]>, reindex: bool) -> PieceManager {
+        if !reindex {
+            if let Some(cached) = Self::load_cache(cache_path) {
+                let current = Self::fingerprint_dir(dir, ext_filter);
+                if current == cached.file_index {
+                    eprintln!("   [Cache] Hit: {} files unchanged, reusing index.", current.len());
+                    return cached;
+                }
+                let changed = current
+                    .iter()
+                    .filter(|(p, fp)| cached.file_index.get(*p) != Some(*fp))
+                    .count();
+                let deleted = cached
+                    .file_index
+                    .keys()
+                    .filter(|p| !current.contains_key(*p))
+                    .count();
+                eprintln!(
+                    "   [Cache] Stale: {} changed/new, {} deleted — rebuilding.",
+                    changed, deleted
+                );
+            }
+        }
+
+        let manager = Self::from_directory_filtered(dir, ext_filter);
+        if let Err(e) = manager.save_cache(cache_path) {
+            eprintln!("   [Cache] Warning: could not write {}: {}", cache_path.display(), e);
+        }
+        manager
+    }
+
+    /// Serialize the full index to `cache_path` as JSON (pieces, embeddings,
+    /// vocab, idf, BM25 params, and file fingerprints). The inverted index is
+    /// intentionally not stored — it is rebuilt on load.
+    pub fn save_cache(&self, cache_path: &Path) -> std::io::Result<()> {
+        let json = serde_json::to_string(self)
+            .map_err(std::io::Error::other)?;
+        fs::write(cache_path, json)
     }
 
-    /// Walk a directory recursively, reading text files and splitting into chunks.
-    fn walk_dir(dir: &Path, chunks: &mut Vec<(String, PathBuf, usize)>, ext_filter: Option<&[&str]>) {
+    /// Load an index previously written by `save_cache`, rebuilding the
+    /// derived inverted index. Returns `None` when the cache is absent or
+    /// cannot be parsed, so the caller falls back to a full build.
+    pub fn load_cache(cache_path: &Path) -> Option<PieceManager> {
+        let data = fs::read_to_string(cache_path).ok()?;
+        let mut manager: PieceManager = serde_json::from_str(&data).ok()?;
+        manager.postings = Self::build_postings(&manager.pieces);
+        Some(manager)
+    }
+
+    /// Fingerprint every indexable file under `dir` without chunking or
+    /// embedding, for a cheap unchanged/changed comparison against the cache.
+    fn fingerprint_dir(dir: &Path, ext_filter: Option<&[&str]>) -> HashMap<String, FileFingerprint> {
+        let mut index = HashMap::new();
+        Self::fingerprint_walk(dir, &mut index, ext_filter);
+        index
+    }
+
+    /// Recursive traversal mirroring `walk_dir`'s directory/extension filter,
+    /// but recording only each file's content hash and mtime.
+    fn fingerprint_walk(dir: &Path, index: &mut HashMap<String, FileFingerprint>, ext_filter: Option<&[&str]>) {
         let entries = match fs::read_dir(dir) {
             Ok(e) => e,
             Err(_) => return,
@@ -97,13 +286,128 @@ impl PieceManager {
             if path.is_dir() {
                 let name = path.file_name().unwrap_or_default().to_string_lossy();
                 if !name.starts_with('.') && name != "target" {
-                    Self::walk_dir(&path, chunks, ext_filter);
+                    Self::fingerprint_walk(&path, index, ext_filter);
                 }
             } else if path.is_file() {
                 let ext = path.extension().unwrap_or_default().to_string_lossy();
                 if allowed.iter().any(|a| *a == ext.as_ref()) {
                     if let Ok(content) = fs::read_to_string(&path) {
-                        let file_chunks = Self::split_into_chunks(&content, 80);
+                        index.insert(path.display().to_string(), Self::fingerprint_file(&content, &path));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Build a `FileFingerprint` from a file's content and metadata.
+    fn fingerprint_file(content: &str, path: &Path) -> FileFingerprint {
+        let mtime = fs::metadata(path)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        FileFingerprint {
+            hash: Self::hash_content(content),
+            mtime,
+        }
+    }
+
+    /// Build the inverted index from each piece's nonzero embedding
+    /// dimensions: `term_id → [(piece_id, weight)]`.
+    fn build_postings(pieces: &[Piece]) -> HashMap<usize, Vec<(usize, f64)>> {
+        let mut postings: HashMap<usize, Vec<(usize, f64)>> = HashMap::new();
+        for piece in pieces {
+            for (dim, &w) in piece.embedding.kf_data().iter().enumerate() {
+                if w != 0.0 {
+                    postings.entry(dim).or_default().push((piece.id, w));
+                }
+            }
+        }
+        postings
+    }
+
+    /// Score a query against the corpus via the inverted index.
+    ///
+    /// Query vectors are extremely sparse, so rather than dotting the query
+    /// against every piece's dense embedding, we walk only the posting lists
+    /// of the query's nonzero terms and accumulate partial dot products into a
+    /// candidate map — pieces that share no term with the query are never
+    /// touched and implicitly score zero. The surviving candidates are reduced
+    /// to the top `top_k` with a bounded min-heap. Returns `(piece_id, score)`
+    /// pairs sorted by descending score.
+    #[allow(dead_code)] // single-thread posting-list query path; the swarm is the default ranker
+    pub fn query_index(&self, query: &K, top_k: usize) -> Vec<(usize, f64)> {
+        if top_k == 0 {
+            return Vec::new();
+        }
+
+        let qf = query.kf_data();
+        let mut scores: HashMap<usize, f64> = HashMap::new();
+        for (dim, &qw) in qf.iter().enumerate() {
+            if qw == 0.0 {
+                continue;
+            }
+            if let Some(list) = self.postings.get(&dim) {
+                for &(piece_id, w) in list {
+                    *scores.entry(piece_id).or_insert(0.0) += qw * w;
+                }
+            }
+        }
+
+        // Bounded min-heap: keep only the best `top_k` candidates, evicting the
+        // current minimum whenever a stronger candidate arrives.
+        let mut heap: BinaryHeap<Reverse<Candidate>> = BinaryHeap::with_capacity(top_k + 1);
+        for (piece_id, score) in scores {
+            if score <= 0.0 {
+                continue;
+            }
+            let cand = Candidate { score, piece_id };
+            if heap.len() < top_k {
+                heap.push(Reverse(cand));
+            } else if let Some(Reverse(min)) = heap.peek() {
+                if cand > *min {
+                    heap.pop();
+                    heap.push(Reverse(cand));
+                }
+            }
+        }
+
+        let mut out: Vec<(usize, f64)> = heap
+            .into_iter()
+            .map(|Reverse(c)| (c.piece_id, c.score))
+            .collect();
+        out.sort_by(|a, b| b.1.total_cmp(&a.1));
+        out
+    }
+
+    /// Walk a directory recursively, reading text files and splitting into
+    /// chunks, while recording each file's fingerprint for the on-disk cache.
+    fn walk_dir(
+        dir: &Path,
+        chunks: &mut Vec<(String, PathBuf, usize)>,
+        file_index: &mut HashMap<String, FileFingerprint>,
+        ext_filter: Option<&[&str]>,
+    ) {
+        let entries = match fs::read_dir(dir) {
+            Ok(e) => e,
+            Err(_) => return,
+        };
+        let default_exts: &[&str] = &["rs", "toml", "md", "txt", "c", "h", "py", "js", "ts"];
+        let allowed = ext_filter.unwrap_or(default_exts);
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                if !name.starts_with('.') && name != "target" {
+                    Self::walk_dir(&path, chunks, file_index, ext_filter);
+                }
+            } else if path.is_file() {
+                let ext = path.extension().unwrap_or_default().to_string_lossy();
+                if allowed.iter().any(|a| *a == ext.as_ref()) {
+                    if let Ok(content) = fs::read_to_string(&path) {
+                        file_index.insert(path.display().to_string(), Self::fingerprint_file(&content, &path));
+                        let file_chunks = Self::split_into_chunks(&content, ext.as_ref(), 80);
                         for (chunk_text, line_num) in file_chunks {
                             if !chunk_text.trim().is_empty() {
                                 chunks.push((chunk_text, path.clone(), line_num));
@@ -118,8 +422,143 @@ impl PieceManager {
     /// Split content into chunks of approximately `max_lines` lines.
     ///
     /// Returns Vec<(chunk_text, start_line)> where start_line is 1-indexed.
-    /// Tries to split at function/struct boundaries when possible.
-    fn split_into_chunks(content: &str, max_lines: usize) -> Vec<(String, usize)> {
+    ///
+    /// Prefers an AST-aware split: when a tree-sitter grammar is available
+    /// for `ext`, the file is parsed and one chunk is emitted per top-level
+    /// semantic unit (function, method, struct/class, impl block), with the
+    /// node's true start line and byte span. A node larger than `max_lines`
+    /// is broken down by recursing into its own children. When no grammar is
+    /// available — or parsing yields nothing useful — we fall back to the
+    /// line-based splitter below, which only counts lines and guesses at
+    /// boundaries.
+    fn split_into_chunks(content: &str, ext: &str, max_lines: usize) -> Vec<(String, usize)> {
+        if let Some(language) = Self::grammar_for_ext(ext) {
+            if let Some(chunks) = Self::split_ast(content, language, max_lines) {
+                return chunks;
+            }
+        }
+        Self::split_line_based(content, max_lines)
+    }
+
+    /// Select a tree-sitter grammar from a file extension.
+    ///
+    /// Returns `None` for extensions we have no parser for; the caller then
+    /// falls back to the line-based splitter.
+    fn grammar_for_ext(ext: &str) -> Option<tree_sitter::Language> {
+        match ext {
+            "rs" => Some(tree_sitter_rust::language()),
+            "py" => Some(tree_sitter_python::language()),
+            "js" => Some(tree_sitter_javascript::language()),
+            "ts" => Some(tree_sitter_typescript::language_typescript()),
+            "c" | "h" => Some(tree_sitter_c::language()),
+            _ => None,
+        }
+    }
+
+    /// Parse `content` with the given grammar and emit one chunk per
+    /// top-level semantic node, recording each node's true 1-indexed start
+    /// line and byte span. Returns `None` if parsing fails or produces no
+    /// named nodes, so the caller can fall back to the line-based splitter.
+    fn split_ast(content: &str, language: tree_sitter::Language, max_lines: usize) -> Option<Vec<(String, usize)>> {
+        let mut parser = tree_sitter::Parser::new();
+        parser.set_language(language).ok()?;
+        let tree = parser.parse(content, None)?;
+
+        let mut chunks = Vec::new();
+        let root = tree.root_node();
+        let mut cursor = root.walk();
+        for child in root.named_children(&mut cursor) {
+            // Only top-level semantic units become pieces; `use`/`const`/`mod`
+            // declarations, attributes, and comments are skipped so the index
+            // isn't polluted with one-line noise.
+            if Self::is_semantic_unit(child.kind()) {
+                Self::emit_node(child, content, max_lines, &mut chunks);
+            }
+        }
+
+        if chunks.is_empty() {
+            None
+        } else {
+            Some(chunks)
+        }
+    }
+
+    /// Emit `node` as a single chunk, or — when it is an oversized impl/class/
+    /// trait body — split it into its member units so a large block becomes one
+    /// piece per method rather than one giant piece. Recursion stops at the
+    /// declaration level: a function is always emitted whole, never shredded
+    /// into its parameter list and individual statements.
+    fn emit_node(node: tree_sitter::Node, content: &str, max_lines: usize, chunks: &mut Vec<(String, usize)>) {
+        let start_line = node.start_position().row + 1; // tree-sitter rows are 0-indexed
+        let end_line = node.end_position().row + 1;
+        let span = end_line - start_line + 1;
+
+        if span > max_lines && Self::is_container(node.kind()) {
+            let before = chunks.len();
+            Self::emit_members(node, content, max_lines, chunks);
+            if chunks.len() > before {
+                return; // members were emitted in place of the whole block
+            }
+        }
+
+        let text = content[node.start_byte()..node.end_byte()].to_string();
+        chunks.push((text, start_line));
+    }
+
+    /// Walk a container's body for its member semantic units, descending
+    /// through wrapper nodes (a declaration list, class body, or block) that
+    /// are not themselves pieces, and emit each member via `emit_node`.
+    fn emit_members(node: tree_sitter::Node, content: &str, max_lines: usize, chunks: &mut Vec<(String, usize)>) {
+        let mut cursor = node.walk();
+        for child in node.named_children(&mut cursor) {
+            if Self::is_semantic_unit(child.kind()) {
+                Self::emit_node(child, content, max_lines, chunks);
+            } else if child.named_child_count() > 0 {
+                Self::emit_members(child, content, max_lines, chunks);
+            }
+        }
+    }
+
+    /// Node kinds that stand on their own as a piece — functions, methods,
+    /// structs/classes, impls, and the like — across the grammars we parse
+    /// (Rust, Python, JavaScript, TypeScript, C).
+    fn is_semantic_unit(kind: &str) -> bool {
+        matches!(
+            kind,
+            // Rust
+            "function_item" | "struct_item" | "enum_item" | "union_item"
+                | "trait_item" | "impl_item"
+            // Python
+            | "function_definition" | "class_definition" | "decorated_definition"
+            // JavaScript / TypeScript
+            | "function_declaration" | "generator_function_declaration"
+                | "class_declaration" | "abstract_class_declaration"
+                | "method_definition" | "interface_declaration" | "enum_declaration"
+            // C
+            | "struct_specifier" | "enum_specifier" | "union_specifier"
+        )
+    }
+
+    /// Semantic units that hold other units and so may be split into their
+    /// members when oversized (an impl/class/trait/interface body), as opposed
+    /// to a function, which is always emitted whole.
+    fn is_container(kind: &str) -> bool {
+        matches!(
+            kind,
+            "impl_item"
+                | "trait_item"
+                | "class_definition"
+                | "class_declaration"
+                | "abstract_class_declaration"
+                | "interface_declaration"
+        )
+    }
+
+    /// Line-based fallback splitter: split content into chunks of
+    /// approximately `max_lines` lines, guessing at boundaries.
+    ///
+    /// Used when no tree-sitter grammar matches the file's extension.
+    fn split_line_based(content: &str, max_lines: usize) -> Vec<(String, usize)> {
         let lines: Vec<&str> = content.lines().collect();
 
         if lines.len() <= max_lines {
@@ -168,6 +607,44 @@ impl PieceManager {
         hasher.finish()
     }
 
+    /// Compute a 64-bit SimHash fingerprint of a piece's tokens.
+    ///
+    /// Unlike `hash_content`, whose output flips entirely on a one-token edit,
+    /// a SimHash is a locality-sensitive sketch: two pieces that share most of
+    /// their tokens land a small Hamming distance apart. For every token we
+    /// hash it to 64 bits and, for each bit position, add the token's term
+    /// frequency when that bit is 1 and subtract it when 0; the sign of each
+    /// accumulator slot becomes the corresponding output bit.
+    fn compute_simhash(content: &str) -> u64 {
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for token in Self::tokenize(content) {
+            *counts.entry(token).or_insert(0) += 1;
+        }
+
+        let mut acc = [0_i64; 64];
+        for (token, tf) in &counts {
+            let mut hasher = DefaultHasher::new();
+            token.hash(&mut hasher);
+            let bits = hasher.finish();
+            let tf = *tf as i64;
+            for (bit, slot) in acc.iter_mut().enumerate() {
+                if (bits >> bit) & 1 == 1 {
+                    *slot += tf;
+                } else {
+                    *slot -= tf;
+                }
+            }
+        }
+
+        let mut fingerprint = 0_u64;
+        for (bit, &slot) in acc.iter().enumerate() {
+            if slot > 0 {
+                fingerprint |= 1 << bit;
+            }
+        }
+        fingerprint
+    }
+
     /// Build vocabulary from all chunks — extract unique terms.
     /// Uses document frequency (how many chunks contain each term) for filtering.
     fn build_vocab(chunks: &[(String, PathBuf, usize)]) -> Vec<String> {
@@ -210,18 +687,67 @@ impl PieceManager {
         result
     }
 
-    /// Compute a TF-IDF-style embedding for a piece of text.
+    /// Compute per-term inverse document frequency and the average document
+    /// length, both needed for BM25 scoring.
+    ///
+    /// Returns `(idf, avgdl)` where `idf` is aligned to `vocab` (one entry per
+    /// term) and `avgdl` is the mean chunk length in tokens. `idf` uses the
+    /// BM25 "plus one" form so weights stay non-negative even for terms that
+    /// occur in more than half the corpus:
+    ///   idf(t) = ln((N - df + 0.5)/(df + 0.5) + 1)
+    fn compute_bm25_stats(chunks: &[(String, PathBuf, usize)], vocab: &[String]) -> (Vec<f64>, f64) {
+        let n = chunks.len();
+
+        // Document frequency of each vocab term, plus total token count for avgdl.
+        let mut index: HashMap<&str, usize> = HashMap::with_capacity(vocab.len());
+        for (i, term) in vocab.iter().enumerate() {
+            index.insert(term.as_str(), i);
+        }
+        let mut doc_freq = vec![0_usize; vocab.len()];
+        let mut total_tokens = 0_usize;
+
+        for (content, _, _) in chunks {
+            let tokens = Self::tokenize(content);
+            total_tokens += tokens.len();
+            let mut seen_in_chunk: std::collections::HashSet<usize> = std::collections::HashSet::new();
+            for token in &tokens {
+                if let Some(&i) = index.get(token.as_str()) {
+                    seen_in_chunk.insert(i);
+                }
+            }
+            for i in seen_in_chunk {
+                doc_freq[i] += 1;
+            }
+        }
+
+        let nf = n as f64;
+        let idf: Vec<f64> = doc_freq
+            .iter()
+            .map(|&df| {
+                let df = df as f64;
+                ((nf - df + 0.5) / (df + 0.5) + 1.0).ln()
+            })
+            .collect();
+
+        let avgdl = if n == 0 { 0.0 } else { total_tokens as f64 / nf };
+        (idf, avgdl)
+    }
+
+    /// Compute a BM25 embedding for a piece of text.
     ///
-    /// Returns a K float array (from k.rs) so we can use _dot directly
-    /// from va.rs for relevance scoring. No ML model needed — just
-    /// term frequency vectors.
-    fn compute_embedding(content: &str, vocab: &[String]) -> K {
+    /// Returns a K float array (from k.rs) so we can use _dot directly from
+    /// va.rs for relevance scoring. Each nonzero dimension holds the BM25
+    /// document-side weight of the corresponding vocab term:
+    ///   idf(t) * (tf * (k1 + 1)) / (tf + k1 * (1 - b + b * |d| / avgdl))
+    /// Paired with the binary presence query vector from `embed_query`, the
+    /// `_dot` of the two vectors is exactly the BM25 score of the piece.
+    fn compute_embedding(content: &str, vocab: &[String], idf: &[f64], avgdl: f64, k1: f64, b: f64) -> K {
         let tokens = Self::tokenize(content);
-        let total = tokens.len() as f64;
+        let len = tokens.len() as f64;
 
         let mut vec = vec![0.0_f64; vocab.len()];
 
-        if total == 0.0 {
+        if len == 0.0 {
             return K::from_floats(vec);
         }
 
@@ -231,27 +757,116 @@ impl PieceManager {
             *counts.entry(token.as_str()).or_insert(0) += 1;
         }
 
-        // Build TF vector (normalized by document length)
+        // BM25 weight per term present in this piece. avgdl==0 only when the
+        // corpus is empty, in which case no term is present anyway.
+        let norm = if avgdl > 0.0 { len / avgdl } else { 0.0 };
         for (i, term) in vocab.iter().enumerate() {
             if let Some(&count) = counts.get(term.as_str()) {
-                vec[i] = count as f64 / total;
+                let tf = count as f64;
+                vec[i] = idf[i] * (tf * (k1 + 1.0)) / (tf + k1 * (1.0 - b + b * norm));
             }
         }
 
-        // Normalize to unit length (so _dot gives cosine similarity)
-        let magnitude: f64 = vec.iter().map(|x| x * x).sum::<f64>().sqrt();
-        if magnitude > 0.0 {
-            for v in &mut vec {
-                *v /= magnitude;
+        K::from_floats(vec)
+    }
+
+    /// Convert query text to a binary presence vector.
+    ///
+    /// Each vocab term present in `text` contributes `1.0`; absent terms stay
+    /// zero. The `idf` lives on the document side only (it already scales each
+    /// BM25 weight in `compute_embedding`), so `_dot` of this vector with a
+    /// piece's embedding is exactly the piece's BM25 score — weighting the
+    /// query side by `idf` too would square it and break that invariant.
+    pub fn embed_query(&self, text: &str) -> K {
+        let mut vec = vec![0.0_f64; self.vocab.len()];
+        let tokens = Self::tokenize(text);
+        let present: std::collections::HashSet<&str> = tokens.iter().map(|t| t.as_str()).collect();
+        for (i, term) in self.vocab.iter().enumerate() {
+            if present.contains(term.as_str()) {
+                vec[i] = 1.0;
             }
         }
+        K::from_floats(vec)
+    }
 
+    /// Convert query text to a presence vector, tolerating typos.
+    ///
+    /// Like `embed_query`, the query side carries no idf — an exact hit sets its
+    /// dimension to 1.0 so `_dot` stays the BM25 score (the idf already lives in
+    /// the document weights). A query token with no exact vocab hit is matched
+    /// fuzzily: every vocab term within bounded Levenshtein distance folds its
+    /// dimension into the query vector with a weight that decays by edit
+    /// distance (1.0 exact, 0.5 at distance 1, 0.25 at distance 2), so a near
+    /// miss contributes a fraction of a full presence. The allowed distance is 1
+    /// for short tokens (≤ 5 chars) and 2 for longer ones, so a single
+    /// misspelling no longer silently drops the term.
+    pub fn embed_query_fuzzy(&self, text: &str) -> K {
+        let mut vec = vec![0.0_f64; self.vocab.len()];
+        for token in Self::tokenize(text) {
+            // Exact hit — vocab is sorted, so a binary search suffices.
+            if let Ok(pos) = self.vocab.binary_search(&token) {
+                if 1.0 > vec[pos] {
+                    vec[pos] = 1.0;
+                }
+                continue;
+            }
+
+            // No exact hit: scan for terms within the allowed edit distance.
+            let max_dist = if token.chars().count() <= 5 { 1 } else { 2 };
+            for (i, term) in self.vocab.iter().enumerate() {
+                if let Some(d) = Self::bounded_levenshtein(&token, term, max_dist) {
+                    let decay = match d {
+                        0 => 1.0,
+                        1 => 0.5,
+                        _ => 0.25,
+                    };
+                    if decay > vec[i] {
+                        vec[i] = decay;
+                    }
+                }
+            }
+        }
         K::from_floats(vec)
     }
 
-    /// Convert text to embedding (public, for queries).
-    pub fn embed_query(&self, text: &str) -> K {
-        Self::compute_embedding(text, &self.vocab)
+    /// Levenshtein distance between `a` and `b`, bounded by `max`.
+    ///
+    /// Returns `Some(distance)` when the edit distance is `≤ max`, else `None`.
+    /// The DP computes full rows and aborts after any row whose minimum exceeds
+    /// `max`, so candidate rejection stays cheap even against a 20k-term vocab.
+    fn bounded_levenshtein(a: &str, b: &str, max: usize) -> Option<usize> {
+        let a: Vec<char> = a.chars().collect();
+        let b: Vec<char> = b.chars().collect();
+        let (la, lb) = (a.len(), b.len());
+
+        // A length gap larger than `max` cannot be closed by ≤ max edits.
+        if la.abs_diff(lb) > max {
+            return None;
+        }
+
+        let mut prev: Vec<usize> = (0..=lb).collect();
+        for i in 1..=la {
+            let mut cur = vec![0_usize; lb + 1];
+            cur[0] = i;
+            let mut row_min = cur[0];
+            for j in 1..=lb {
+                let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+                cur[j] = (prev[j] + 1).min(cur[j - 1] + 1).min(prev[j - 1] + cost);
+                row_min = row_min.min(cur[j]);
+            }
+            // Every future row can only grow this minimum — abort early.
+            if row_min > max {
+                return None;
+            }
+            prev = cur;
+        }
+
+        let d = prev[lb];
+        if d <= max {
+            Some(d)
+        } else {
+            None
+        }
     }
 
     /// Convert text to a sequence of Token IDs.
@@ -271,6 +886,67 @@ impl PieceManager {
         ids
     }
 
+    /// Resolve a boolean query tree to the set of matching piece IDs.
+    ///
+    /// `And` intersects, `Or` unions, `Not` takes the difference from the full
+    /// piece universe, and `Phrase` matches only when the quoted tokens appear
+    /// as a consecutive run in a piece's tokenized content. The swarm then
+    /// ranks just these survivors, so the candidate set is a hard filter on
+    /// top of the soft similarity score.
+    pub fn evaluate_query(&self, op: &Op) -> HashSet<usize> {
+        match op {
+            Op::Term(term) => self
+                .pieces
+                .iter()
+                .filter(|p| Self::tokenize(&p.content).iter().any(|tok| tok == term))
+                .map(|p| p.id)
+                .collect(),
+            Op::Phrase(words) => self
+                .pieces
+                .iter()
+                .filter(|p| Self::contains_phrase(&Self::tokenize(&p.content), words))
+                .map(|p| p.id)
+                .collect(),
+            Op::Not(inner) => {
+                let excluded = self.evaluate_query(inner);
+                self.pieces
+                    .iter()
+                    .map(|p| p.id)
+                    .filter(|id| !excluded.contains(id))
+                    .collect()
+            }
+            Op::And(ops) => {
+                let mut iter = ops.iter();
+                let mut acc = match iter.next() {
+                    Some(first) => self.evaluate_query(first),
+                    None => return HashSet::new(),
+                };
+                for op in iter {
+                    let next = self.evaluate_query(op);
+                    acc.retain(|id| next.contains(id));
+                }
+                acc
+            }
+            Op::Or(ops) => {
+                let mut acc = HashSet::new();
+                for op in ops {
+                    acc.extend(self.evaluate_query(op));
+                }
+                acc
+            }
+        }
+    }
+
+    /// True when `words` appear as a consecutive run inside `tokens`.
+    fn contains_phrase(tokens: &[String], words: &[String]) -> bool {
+        if words.is_empty() || words.len() > tokens.len() {
+            return false;
+        }
+        tokens
+            .windows(words.len())
+            .any(|window| window == words)
+    }
+
     /// Simple tokenizer — split on non-alphanumeric, lowercase, filter short.
     fn tokenize(content: &str) -> Vec<String> {
         content
@@ -286,9 +962,89 @@ impl PieceManager {
         self.hash_index.len()
     }
 
-    /// How many duplicate pieces detected?
-    pub fn dupe_count(&self) -> usize {
-        self.pieces.len() - self.unique_count()
+    /// How many duplicate pieces detected, counting both exact-hash matches
+    /// and SimHash near-duplicates within `threshold` Hamming distance.
+    ///
+    /// Exact dupes and near-dupes are merged into shared groups (a piece that
+    /// is an exact copy of A and a near-copy of B links A and B transitively),
+    /// so the count is `pieces.len()` minus the number of distinct groups. A
+    /// `threshold` of 0 still merges pieces with identical SimHash fingerprints,
+    /// such as pieces with the same tokens but different whitespace or
+    /// punctuation, so it can exceed the pure exact-hash count.
+    pub fn dupe_count(&self, threshold: u32) -> usize {
+        let n = self.pieces.len();
+        if n == 0 {
+            return 0;
+        }
+        let mut dsu = Dsu::new(n);
+        for ids in self.hash_index.values() {
+            for pair in ids.windows(2) {
+                dsu.union(pair[0], pair[1]);
+            }
+        }
+        for (a, b) in self.simhash_candidate_pairs() {
+            if (self.pieces[a].simhash ^ self.pieces[b].simhash).count_ones() <= threshold {
+                dsu.union(a, b);
+            }
+        }
+        n - dsu.group_count()
+    }
+
+    /// Group pieces that are SimHash near-duplicates within `threshold`
+    /// Hamming distance, returning one sorted id list per group of size ≥ 2.
+    ///
+    /// Candidate pairs are found by banding — the 64-bit fingerprint is split
+    /// into 4 bands of 16 bits and pieces sharing any band value are compared —
+    /// so we avoid the all-pairs `O(n²)` Hamming scan. Surviving candidates are
+    /// merged transitively, matching how `dupe_count` folds near-dupes in.
+    pub fn near_dupe_groups(&self, threshold: u32) -> Vec<Vec<usize>> {
+        let n = self.pieces.len();
+        let mut dsu = Dsu::new(n);
+        for (a, b) in self.simhash_candidate_pairs() {
+            if (self.pieces[a].simhash ^ self.pieces[b].simhash).count_ones() <= threshold {
+                dsu.union(a, b);
+            }
+        }
+
+        let mut groups: HashMap<usize, Vec<usize>> = HashMap::new();
+        for id in 0..n {
+            groups.entry(dsu.find(id)).or_default().push(id);
+        }
+        let mut out: Vec<Vec<usize>> = groups
+            .into_values()
+            .filter(|g| g.len() >= 2)
+            .map(|mut g| {
+                g.sort_unstable();
+                g
+            })
+            .collect();
+        out.sort_unstable_by_key(|g| g[0]);
+        out
+    }
+
+    /// Candidate near-duplicate pairs from SimHash banding: split each 64-bit
+    /// fingerprint into 4 bands of 16 bits and bucket pieces by band value;
+    /// any two pieces sharing a band value are a candidate pair the caller
+    /// verifies by Hamming distance. Pairs are de-duplicated across bands.
+    fn simhash_candidate_pairs(&self) -> HashSet<(usize, usize)> {
+        let mut buckets: HashMap<(u8, u16), Vec<usize>> = HashMap::new();
+        for piece in &self.pieces {
+            for band in 0..4u8 {
+                let value = (piece.simhash >> (band * 16)) as u16;
+                buckets.entry((band, value)).or_default().push(piece.id);
+            }
+        }
+
+        let mut pairs: HashSet<(usize, usize)> = HashSet::new();
+        for ids in buckets.values() {
+            for i in 0..ids.len() {
+                for j in (i + 1)..ids.len() {
+                    let (a, b) = (ids[i].min(ids[j]), ids[i].max(ids[j]));
+                    pairs.insert((a, b));
+                }
+            }
+        }
+        pairs
     }
 
     /// Vocabulary size (embedding dimension).
@@ -0,0 +1,216 @@
+//! Boolean & phrase query language — parser and operation tree.
+//!
+//! Turns input like
+//!   `dot product AND "fused multiply" AND NOT gpu`
+//! into an `Op` tree that `PieceManager::evaluate_query` resolves to a
+//! candidate set of piece IDs. The swarm then ranks *only* the survivors
+//! with the existing embedding `_dot`, so a query can require a term,
+//! exclude one, or match a quoted phrase rather than only scoring soft
+//! similarity over a flat bag of words.
+//!
+//! Precedence (loosest to tightest): `OR`, `AND` (explicit or implicit from
+//! adjacency), `NOT`, atom. Bare adjacent words are implicitly ANDed.
+
+/// A node in a parsed boolean query.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Op {
+    /// All children must match (set intersection).
+    And(Vec<Op>),
+    /// Any child may match (set union).
+    Or(Vec<Op>),
+    /// The child must NOT match (set difference from the universe).
+    Not(Box<Op>),
+    /// The quoted tokens must appear as a consecutive run inside a piece.
+    Phrase(Vec<String>),
+    /// A single term; a piece matches when it contains the token.
+    Term(String),
+}
+
+impl Op {
+    /// Collect the positive terms of the query — everything under an `And`,
+    /// `Or`, `Term`, or `Phrase`, but nothing inside a `Not`. These drive the
+    /// similarity ranking of the surviving candidates (an excluded term should
+    /// not also pull a piece up the ranking).
+    pub fn ranking_terms(&self) -> Vec<String> {
+        let mut out = Vec::new();
+        self.collect_ranking_terms(&mut out);
+        out
+    }
+
+    fn collect_ranking_terms(&self, out: &mut Vec<String>) {
+        match self {
+            Op::Term(t) => out.push(t.clone()),
+            Op::Phrase(words) => out.extend(words.iter().cloned()),
+            Op::And(ops) | Op::Or(ops) => {
+                for op in ops {
+                    op.collect_ranking_terms(out);
+                }
+            }
+            Op::Not(_) => {} // excluded terms never contribute to ranking
+        }
+    }
+}
+
+/// A lexeme produced by the tokenizer.
+enum Lex {
+    And,
+    Or,
+    Not,
+    Word(String),
+    Phrase(Vec<String>),
+}
+
+/// Parse a query string into an `Op` tree.
+///
+/// Returns `None` when the input carries no searchable term (e.g. empty or
+/// only stop punctuation) or has an operator with no operand (e.g. `foo AND`),
+/// letting the caller fall back to plain similarity.
+pub fn parse(input: &str) -> Option<Op> {
+    let lexemes = lex(input);
+    if lexemes.is_empty() {
+        return None;
+    }
+    let mut parser = Parser { lexemes, pos: 0 };
+    parser.parse_or()
+}
+
+/// Split a query string into lexemes, recognizing the `AND`/`OR`/`NOT`
+/// keywords (uppercase only, so a lowercase `and` stays a search term) and
+/// double-quoted phrases.
+fn lex(input: &str) -> Vec<Lex> {
+    let mut out = Vec::new();
+    let chars: Vec<char> = input.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        if c == '"' {
+            // Consume up to the closing quote (or end of input).
+            i += 1;
+            let start = i;
+            while i < chars.len() && chars[i] != '"' {
+                i += 1;
+            }
+            let phrase: String = chars[start..i].iter().collect();
+            if i < chars.len() {
+                i += 1; // skip closing quote
+            }
+            let words = tokenize_corpus(&phrase);
+            if !words.is_empty() {
+                out.push(Lex::Phrase(words));
+            }
+            continue;
+        }
+        // Bare word — read until whitespace or a quote.
+        let start = i;
+        while i < chars.len() && !chars[i].is_whitespace() && chars[i] != '"' {
+            i += 1;
+        }
+        let word: String = chars[start..i].iter().collect();
+        match word.as_str() {
+            "AND" => out.push(Lex::And),
+            "OR" => out.push(Lex::Or),
+            "NOT" => out.push(Lex::Not),
+            // Run the bare word through the corpus tokenizer too, so a short or
+            // numeric lexeme (e.g. `x` or `2`) is filtered out instead of
+            // becoming a `Term` that can never match a piece. A word that
+            // splits into several corpus tokens becomes an implicit AND.
+            _ => {
+                for tok in tokenize_corpus(&word) {
+                    out.push(Lex::Word(tok));
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Tokenize text exactly as `PieceManager::tokenize` does — split on
+/// non-alphanumeric (keeping `_`), drop tokens shorter than two chars and
+/// all-numeric tokens, then lowercase — so query tokens line up with a
+/// piece's token stream. Used for both quoted phrases and bare words.
+fn tokenize_corpus(s: &str) -> Vec<String> {
+    s.split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|t| t.len() >= 2)
+        .filter(|t| !t.chars().all(char::is_numeric))
+        .map(|t| t.to_lowercase())
+        .collect()
+}
+
+struct Parser {
+    lexemes: Vec<Lex>,
+    pos: usize,
+}
+
+impl Parser {
+    /// or := and ( OR and )*
+    fn parse_or(&mut self) -> Option<Op> {
+        let mut terms = vec![self.parse_and()?];
+        while matches!(self.peek(), Some(Lex::Or)) {
+            self.pos += 1;
+            terms.push(self.parse_and()?);
+        }
+        Some(if terms.len() == 1 {
+            terms.pop().unwrap()
+        } else {
+            Op::Or(terms)
+        })
+    }
+
+    /// and := not ( [AND] not )*  — adjacency is an implicit AND.
+    fn parse_and(&mut self) -> Option<Op> {
+        let mut terms = vec![self.parse_not()?];
+        loop {
+            match self.peek() {
+                Some(Lex::And) => {
+                    self.pos += 1;
+                    terms.push(self.parse_not()?);
+                }
+                // Implicit AND: another atom/NOT follows without a keyword.
+                Some(Lex::Not) | Some(Lex::Word(_)) | Some(Lex::Phrase(_)) => {
+                    terms.push(self.parse_not()?);
+                }
+                _ => break,
+            }
+        }
+        Some(if terms.len() == 1 {
+            terms.pop().unwrap()
+        } else {
+            Op::And(terms)
+        })
+    }
+
+    /// not := NOT not | atom
+    fn parse_not(&mut self) -> Option<Op> {
+        if matches!(self.peek(), Some(Lex::Not)) {
+            self.pos += 1;
+            return Some(Op::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_atom()
+    }
+
+    /// atom := Word | Phrase
+    fn parse_atom(&mut self) -> Option<Op> {
+        match self.lexemes.get(self.pos) {
+            Some(Lex::Word(w)) => {
+                let op = Op::Term(w.clone());
+                self.pos += 1;
+                Some(op)
+            }
+            Some(Lex::Phrase(words)) => {
+                let op = Op::Phrase(words.clone());
+                self.pos += 1;
+                Some(op)
+            }
+            // A dangling operator with no operand — treat as parse failure.
+            _ => None,
+        }
+    }
+
+    fn peek(&self) -> Option<&Lex> {
+        self.lexemes.get(self.pos)
+    }
+}
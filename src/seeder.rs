@@ -11,19 +11,101 @@
 //!   Top-K results → Best pieces assembled into context
 
 use crate::k::K;
-use crate::piece::PieceManager;
+use crate::piece::{Piece, PieceManager};
 use crate::va;
-use std::sync::mpsc::{channel, Receiver, Sender};
-use std::thread;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::sync::Mutex;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
 
 /// Command sent to worker threads
 pub enum SeederCommand {
-    Query(K),
+    /// Score the shard against `query`, tagged with the issuing query's id so
+    /// the gather loop can demultiplex results from concurrent callers. The
+    /// worker reduces its shard to its own best `top_k` before replying, so the
+    /// channel never carries more than `top_k` results per worker.
+    Query { query_id: u64, query: K, top_k: usize },
+    /// Score the shard against every query in the batch in one command,
+    /// keeping a separate top-K per query. The channel round-trip is shared by
+    /// the whole batch; each query still walks the shard's posting lists on its
+    /// own, so the shard is not traversed once for all queries.
+    QueryBatch { query_id: u64, queries: Vec<K>, top_k: usize },
+    /// Tell the worker to break its receive loop and terminate, so the swarm
+    /// can be torn down cleanly instead of leaking threads until process exit.
+    Shutdown,
 }
 
 /// Result received from worker threads
 pub enum SeederResult {
-    QueryResult(Vec<QueryResult>),
+    /// One worker's answer to query `qid`. `wid` identifies the responding
+    /// worker so the gather loop can detect duplicate or missing responses
+    /// instead of blindly counting to `num_threads`.
+    QueryResult {
+        qid: u64,
+        wid: usize,
+        results: Vec<QueryResult>,
+        /// Running counters for this worker, snapshotted after serving the
+        /// query — read by `query_with_stats`, ignored by plain `query`.
+        stats: WorkerStats,
+    },
+    /// One worker's answer to a batch `qid`: one top-K list per query in the
+    /// batch, aligned to the batch's query order.
+    BatchResult {
+        qid: u64,
+        wid: usize,
+        results: Vec<Vec<QueryResult>>,
+    },
+}
+
+impl SeederResult {
+    /// Query id this result answers — used to demultiplex the shared receiver.
+    fn qid(&self) -> u64 {
+        match self {
+            SeederResult::QueryResult { qid, .. } => *qid,
+            SeederResult::BatchResult { qid, .. } => *qid,
+        }
+    }
+
+    /// Responding worker id.
+    fn wid(&self) -> usize {
+        match self {
+            SeederResult::QueryResult { wid, .. } => *wid,
+            SeederResult::BatchResult { wid, .. } => *wid,
+        }
+    }
+}
+
+/// Cumulative per-worker query counters, for spotting shard imbalance, an
+/// idle seeder, or a pathologically slow shard.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WorkerStats {
+    /// Worker id these counters belong to.
+    pub wid: usize,
+    /// Number of queries this worker has served.
+    pub queries_served: u64,
+    /// Total pieces scanned across all queries.
+    pub pieces_scanned: u64,
+    /// Total pieces that cleared the score threshold and were kept as candidates.
+    pub candidates_emitted: u64,
+    /// Cumulative local scan time, in nanoseconds.
+    pub scan_nanos: u128,
+}
+
+/// A query's results paired with per-worker statistics, returned by
+/// `Swarm::query_with_stats`.
+#[derive(Debug)]
+pub struct SwarmStats {
+    /// Ranked top-K results (same as `query` would return).
+    pub results: Vec<QueryResult>,
+    /// One snapshot per responding worker.
+    pub per_worker: Vec<WorkerStats>,
+    /// Cumulative pieces scanned across the swarm, summed over `per_worker`.
+    pub pieces_scanned: u64,
+    /// Cumulative candidates emitted across the swarm, summed over `per_worker`.
+    pub candidates_emitted: u64,
 }
 
 /// A worker thread that holds a shard of pieces and processes queries.
@@ -33,6 +115,8 @@ pub enum SeederResult {
 pub struct SeederThread {
     /// Channel to send queries TO this worker
     pub tx: Sender<SeederCommand>,
+    /// Stable worker id, echoed back in every `SeederResult`.
+    pub wid: usize,
 }
 
 /// A query result — one piece with its relevance score.
@@ -53,23 +137,178 @@ pub struct QueryResult {
     pub content: String,
 }
 
+/// A scored result inside a worker's bounded top-K min-heap.
+///
+/// Ordered by `score` (via `f64::total_cmp` so NaN never panics the heap),
+/// then by the result's `piece_id` to keep the ordering total and the kept
+/// set deterministic — mirrors `piece::Candidate` on the single-thread path.
+struct Scored {
+    score: f64,
+    result: QueryResult,
+}
+
+impl PartialEq for Scored {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == std::cmp::Ordering::Equal
+    }
+}
+
+impl Eq for Scored {}
+
+impl Ord for Scored {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.score
+            .total_cmp(&other.score)
+            .then(self.result.piece_id.cmp(&other.result.piece_id))
+    }
+}
+
+impl PartialOrd for Scored {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Build a per-shard inverted index: `term dimension → [(shard-local index,
+/// weight)]` over every nonzero embedding weight. Lets a worker touch only the
+/// pieces that share a term with the query instead of dotting the query
+/// against every piece's full dense embedding (the O(pieces×vocab) scan).
+fn build_shard_postings(shard: &[Piece]) -> HashMap<usize, Vec<(usize, f64)>> {
+    let mut postings: HashMap<usize, Vec<(usize, f64)>> = HashMap::new();
+    for (idx, piece) in shard.iter().enumerate() {
+        for (dim, &w) in piece.embedding.kf_data().iter().enumerate() {
+            if w != 0.0 {
+                postings.entry(dim).or_default().push((idx, w));
+            }
+        }
+    }
+    postings
+}
+
+/// The shard-local indices of every piece that shares at least one term with
+/// `query`, gathered from the posting lists of the query's nonzero dimensions.
+/// Pieces with no overlapping term score zero under `_dot`, so skipping them is
+/// exact — this is what turns the O(pieces×vocab) scan into work proportional
+/// to the matched postings. Mirrors the candidate gathering in
+/// `PieceManager::query_index`, which drives the single-thread path.
+fn candidate_pieces(query: &K, postings: &HashMap<usize, Vec<(usize, f64)>>) -> Vec<usize> {
+    let qf = query.kf_data();
+    let mut seen: HashSet<usize> = HashSet::new();
+    for (dim, &qw) in qf.iter().enumerate() {
+        if qw == 0.0 {
+            continue;
+        }
+        if let Some(list) = postings.get(&dim) {
+            for &(idx, _) in list {
+                seen.insert(idx);
+            }
+        }
+    }
+    seen.into_iter().collect()
+}
+
+/// Score one piece embedding against a query vector via the `_dot` verb,
+/// flattening the `K` result back to a scalar `f64`.
+fn score_dot(query: &K, embedding: &K) -> f64 {
+    let dot_res = va::dot(query, embedding);
+    match dot_res.data {
+        crate::k::KData::Floats(v) => v[0],
+        crate::k::KData::Ints(v) => v[0] as f64,
+        _ => 0.0,
+    }
+}
+
+/// Reduce a shard to its best `top_k` scored results using a bounded min-heap,
+/// so a worker emits at most `top_k` entries regardless of shard size.
+fn push_bounded(heap: &mut BinaryHeap<Reverse<Scored>>, cand: Scored, top_k: usize) {
+    if top_k == 0 {
+        return;
+    }
+    if heap.len() < top_k {
+        heap.push(Reverse(cand));
+    } else if let Some(Reverse(min)) = heap.peek() {
+        if cand > *min {
+            heap.pop();
+            heap.push(Reverse(cand));
+        }
+    }
+}
+
+/// How a resilient query should wait on the swarm.
+///
+/// The gather returns as soon as every worker has answered, `min_responses`
+/// workers have answered, or `timeout` elapses — whichever comes first — so a
+/// single stuck or panicked worker can no longer hang the query forever.
+#[derive(Clone, Debug)]
+#[allow(dead_code)] // part of the query-with-options API surface; not yet wired into main
+pub struct QueryOptions {
+    /// Hard deadline for the whole gather.
+    pub timeout: Duration,
+    /// Quorum: return once at least this many workers have answered, even if
+    /// others are still outstanding. Clamped to `[1, num_threads]`.
+    pub min_responses: usize,
+}
+
+/// Outcome of a `query_with` call, carrying both the ranked results and the
+/// coverage the gather actually achieved so the caller can observe when a
+/// worker dropped out.
+#[derive(Debug)]
+#[allow(dead_code)] // returned by query_with, which is not yet wired into main
+pub struct QueryResponse {
+    /// Top-K results assembled from the workers that responded.
+    pub results: Vec<QueryResult>,
+    /// Worker ids that answered within the deadline/quorum.
+    pub responded: Vec<usize>,
+    /// Worker ids that did not answer — nonempty means degraded coverage.
+    pub missing: Vec<usize>,
+}
+
 /// The Swarm — manages the active seeder threads.
 pub struct Swarm {
     /// Active worker threads
     seeders: Vec<SeederThread>,
-    /// Channel to receive results FROM workers
-    result_rx: Receiver<SeederResult>,
+    /// Channel to receive results FROM workers. Behind a `Mutex` because
+    /// `mpsc::Receiver` is `!Sync`: wrapping it makes `Swarm: Sync` so an
+    /// `Arc<Swarm>`/`&Swarm` can be shared across threads, and serializes the
+    /// gather so concurrent callers take turns draining the shared receiver.
+    result_rx: Mutex<Receiver<SeederResult>>,
     /// Number of workers
     num_threads: usize,
     /// Vocabulary size (embedding dimension)
     vocab_size: usize,
+    /// Monotonic source of per-query ids, so concurrent `query` callers on a
+    /// shared `&Swarm` each tag their broadcast with a distinct id.
+    query_counter: AtomicU64,
+    /// Staging area for results that arrive on the shared receiver but belong
+    /// to another in-flight query: `qid → [message]`. A caller drains its own
+    /// qid here first and parks everyone else's messages so a competing query
+    /// (single or batched) never loses its responses.
+    staging: Mutex<HashMap<u64, Vec<SeederResult>>>,
+    /// Query ids currently being gathered. A message whose qid is not in this
+    /// set belongs to a query that already returned (e.g. after a timeout), so
+    /// it is dropped rather than parked in `staging` under a qid no caller will
+    /// ever drain.
+    in_flight: Mutex<std::collections::HashSet<u64>>,
+    /// Worker thread handles, joined on `Drop`/`join` after a `Shutdown`
+    /// broadcast so teardown is deterministic and leaks no threads.
+    handles: Vec<JoinHandle<()>>,
 }
 
 impl Swarm {
     /// Build a swarm from a PieceManager, sharding data across cores.
+    ///
+    /// Uses one worker per logical core (defaulting to 4 if detection fails);
+    /// call `with_threads` to cap the worker count explicitly.
     pub fn from_pieces(manager: &PieceManager) -> Swarm {
-        // 1. Detect cores (default to 4 if detection fails, or use all logical cores)
         let num_threads = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(4);
+        Self::with_threads(manager, num_threads)
+    }
+
+    /// Build a swarm with at most `n` worker threads (clamped to ≥ 1), sharding
+    /// the deduplicated pieces across them. The worker `JoinHandle`s are kept so
+    /// the swarm can be torn down cleanly via `join` or `Drop`.
+    pub fn with_threads(manager: &PieceManager, n: usize) -> Swarm {
+        let num_threads = n.max(1);
         eprintln!("   [Swarm] Spawning {} active seeder threads...", num_threads);
 
         // 2. Partition pieces (deduplicated)
@@ -82,106 +321,443 @@ impl Swarm {
             }
         }
 
-        let total_pieces = unique_pieces.len();
-        let chunk_size = (total_pieces + num_threads - 1) / num_threads; // Ceiling division
+        // Distribute pieces by estimated scan cost rather than by equal
+        // contiguous slices, so a worker that happens to draw denser pieces
+        // doesn't become the straggler every broadcast-gather waits on.
+        let shards = Self::partition_weighted(unique_pieces, num_threads);
 
         // 3. Create channels for results (Many-to-One)
         let (result_tx, result_rx) = channel();
 
         let mut seeders = Vec::with_capacity(num_threads);
+        let mut handles = Vec::with_capacity(num_threads);
 
         // 4. Spawn threads
-        for i in 0..num_threads {
-            // Take a slice of pieces for this thread
-            let start = i * chunk_size;
-            let end = std::cmp::min(start + chunk_size, total_pieces);
-            
-            let shard = if start < total_pieces {
-                unique_pieces[start..end].to_vec()
-            } else {
-                Vec::new() // Threads with no work just stay idle
-            };
-
+        for (i, shard) in shards.into_iter().enumerate() {
             // Channel for sending queries TO this thread
             let (tx, rx) = channel::<SeederCommand>();
             
             // Clone result sender for this thread
             let my_result_tx = result_tx.clone();
             
-            let _handle = thread::spawn(move || {
+            let wid = i;
+            let handle = thread::spawn(move || {
+                // Inverted index over this shard, built once and reused for
+                // every query so scoring walks only the query's posting lists
+                // instead of dotting against every piece's dense embedding.
+                let postings = build_shard_postings(&shard);
+                // Running counters for this worker, accumulated across queries.
+                let mut stats = WorkerStats { wid, ..WorkerStats::default() };
                 // Thread Loop: Wait for queries
                 while let Ok(cmd) = rx.recv() {
                     match cmd {
-                        SeederCommand::Query(query) => {
-                            // "Active Seeder" Logic: Scan my shard
-                            let mut local_results = Vec::with_capacity(shard.len());
-                            
-                            for piece in &shard {
-                                // Compute score (FMA / Dot Product)
-                                let dot_res = va::dot(&query, &piece.embedding);
-                                let score = match dot_res.data {
-                                    crate::k::KData::Floats(v) => v[0],
-                                    crate::k::KData::Ints(v) => v[0] as f64,
-                                    _ => 0.0,
-                                };
+                        SeederCommand::Query { query_id, query, top_k } => {
+                            // "Active Seeder" Logic: the posting lists pick out
+                            // the pieces that share a term with the query, each
+                            // is scored with the `_dot` verb, and a bounded heap
+                            // keeps only my own best `top_k` so the channel never
+                            // carries the full shard's worth of near-useless
+                            // entries.
+                            let mut heap: BinaryHeap<Reverse<Scored>> =
+                                BinaryHeap::with_capacity(top_k + 1);
 
+                            let scan_start = Instant::now();
+                            let candidates = candidate_pieces(&query, &postings);
+                            let scanned = candidates.len() as u64;
+                            let mut emitted = 0_u64;
+                            for idx in candidates {
+                                let piece = &shard[idx];
+                                let score = score_dot(&query, &piece.embedding);
                                 if score > 0.001 { // Optimization: Don't send zero-score noise
-                                     local_results.push(QueryResult {
+                                    emitted += 1;
+                                    let result = QueryResult {
                                         piece_id: piece.id,
                                         score,
                                         source: piece.source.display().to_string(),
                                         start_line: piece.start_line,
                                         preview: piece.content.chars().take(100).collect::<String>(),
                                         content: piece.content.clone(),
-                                    });
+                                    };
+                                    push_bounded(&mut heap, Scored { score, result }, top_k);
                                 }
                             }
-                            // Send my local results back to main thread
-                            let _ = my_result_tx.send(SeederResult::QueryResult(local_results));
+
+                            stats.queries_served += 1;
+                            stats.pieces_scanned += scanned;
+                            stats.candidates_emitted += emitted;
+                            stats.scan_nanos += scan_start.elapsed().as_nanos();
+
+                            let local_results: Vec<QueryResult> =
+                                heap.into_iter().map(|Reverse(s)| s.result).collect();
+                            // Send my local top-K back to main thread, tagged
+                            // with the query id, this worker's id, and stats.
+                            let _ = my_result_tx.send(SeederResult::QueryResult {
+                                qid: query_id,
+                                wid,
+                                results: local_results,
+                                stats,
+                            });
+                        },
+                        SeederCommand::QueryBatch { query_id, queries, top_k } => {
+                            // Score every query in the batch against the shared
+                            // shard posting index in a single command, so the
+                            // broadcast-gather round trip is paid once for the
+                            // whole batch instead of once per query. A separate
+                            // bounded heap reduces each query's results.
+                            let results: Vec<Vec<QueryResult>> = queries
+                                .iter()
+                                .map(|q| {
+                                    let mut heap: BinaryHeap<Reverse<Scored>> =
+                                        BinaryHeap::with_capacity(top_k + 1);
+                                    for idx in candidate_pieces(q, &postings) {
+                                        let piece = &shard[idx];
+                                        let score = score_dot(q, &piece.embedding);
+                                        if score > 0.001 {
+                                            let result = QueryResult {
+                                                piece_id: piece.id,
+                                                score,
+                                                source: piece.source.display().to_string(),
+                                                start_line: piece.start_line,
+                                                preview: piece.content.chars().take(100).collect::<String>(),
+                                                content: piece.content.clone(),
+                                            };
+                                            push_bounded(&mut heap, Scored { score, result }, top_k);
+                                        }
+                                    }
+                                    heap.into_iter().map(|Reverse(s)| s.result).collect()
+                                })
+                                .collect();
+                            let _ = my_result_tx.send(SeederResult::BatchResult {
+                                qid: query_id,
+                                wid,
+                                results,
+                            });
                         },
+                        SeederCommand::Shutdown => break,
                     }
                 }
             });
 
             seeders.push(SeederThread {
                 tx,
+                wid,
             });
+            handles.push(handle);
         }
 
         Swarm {
             seeders,
-            result_rx,
+            result_rx: Mutex::new(result_rx),
             num_threads,
             vocab_size: manager.vocab_size(),
+            query_counter: AtomicU64::new(0),
+            staging: Mutex::new(HashMap::new()),
+            in_flight: Mutex::new(std::collections::HashSet::new()),
+            handles,
         }
     }
 
+    /// Claim a fresh query id and register it as in-flight, so the gather loop
+    /// knows to park — rather than discard — stray messages for it.
+    fn begin_query(&self) -> u64 {
+        let qid = self.query_counter.fetch_add(1, Ordering::Relaxed);
+        self.in_flight.lock().unwrap().insert(qid);
+        qid
+    }
+
+    /// Retire a query id once its gather is done: drop it from the in-flight
+    /// set and evict anything still parked for it, so a straggler that arrives
+    /// later is discarded instead of leaking.
+    fn end_query(&self, qid: u64) {
+        self.in_flight.lock().unwrap().remove(&qid);
+        self.staging.lock().unwrap().remove(&qid);
+    }
+
     /// Parallel Query: Broadcast to all threads, gather results.
+    ///
+    /// Safe to call concurrently on a shared `&Swarm`: each call claims a fresh
+    /// query id, and the gather loop keeps only the responses carrying that id,
+    /// parking any response for a competing query in the staging map so the
+    /// other caller still collects it. A response is accepted once per worker
+    /// id, so a duplicate can't inflate the gather and a query completes when
+    /// every worker has answered.
     pub fn query(&self, query_embedding: &K, top_k: usize) -> Vec<QueryResult> {
-        // 1. Broadcast query to all workers
+        let qid = self.begin_query();
+
+        // 1. Broadcast query to all workers, tagged with our query id.
         for seeder in &self.seeders {
-            let _ = seeder.tx.send(SeederCommand::Query(query_embedding.clone()));
+            let _ = seeder.tx.send(SeederCommand::Query {
+                query_id: qid,
+                query: query_embedding.clone(),
+                top_k,
+            });
         }
 
-        // 2. Gather results from all workers
+        // 2. Gather each worker's response, demultiplexed by query id.
         let mut all_results = Vec::new();
-        for _ in 0..self.num_threads {
-            if let Ok(result) = self.result_rx.recv() {
-                match result {
-                    SeederResult::QueryResult(mut shard_results) => {
-                         all_results.append(&mut shard_results);
-                    },
-                }
+        for msg in self.gather(qid) {
+            if let SeederResult::QueryResult { mut results, .. } = msg {
+                all_results.append(&mut results);
             }
         }
+        self.end_query(qid);
 
         // 3. Sort and truncate (Main thread reduction)
         all_results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
         all_results.truncate(top_k);
-        
+
         all_results
     }
 
+    /// Like `query`, but also returns per-worker statistics.
+    ///
+    /// Each worker folds its running counters into its response, which this
+    /// gathers into a `SwarmStats` alongside the ranked results — giving
+    /// visibility into how much each seeder scanned, how many candidates it
+    /// emitted, and how long its local scan took, so shard imbalance or an idle
+    /// seeder is observable.
+    pub fn query_with_stats(&self, query_embedding: &K, top_k: usize) -> SwarmStats {
+        let qid = self.begin_query();
+
+        for seeder in &self.seeders {
+            let _ = seeder.tx.send(SeederCommand::Query {
+                query_id: qid,
+                query: query_embedding.clone(),
+                top_k,
+            });
+        }
+
+        let mut all_results = Vec::new();
+        let mut per_worker = Vec::with_capacity(self.num_threads);
+        for msg in self.gather(qid) {
+            if let SeederResult::QueryResult { mut results, stats, .. } = msg {
+                all_results.append(&mut results);
+                per_worker.push(stats);
+            }
+        }
+        self.end_query(qid);
+
+        all_results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+        all_results.truncate(top_k);
+
+        // Stable order by worker id so callers can diff successive snapshots.
+        per_worker.sort_by_key(|s| s.wid);
+        let pieces_scanned = per_worker.iter().map(|s| s.pieces_scanned).sum();
+        let candidates_emitted = per_worker.iter().map(|s| s.candidates_emitted).sum();
+
+        SwarmStats {
+            results: all_results,
+            per_worker,
+            pieces_scanned,
+            candidates_emitted,
+        }
+    }
+
+    /// Batched multi-query: one broadcast-gather round trip for all `queries`.
+    ///
+    /// Each seeder scores every query vector against its shared shard posting
+    /// index, so the index is built once and the broadcast-gather round trip is
+    /// paid once for the whole batch instead of per query. Queries are scored
+    /// one after another, each with its own pass over the shard's posting
+    /// lists; the shard is not scanned once for the whole batch. Returns one ranked
+    /// top-`top_k` list per query, aligned to `queries`.
+    #[allow(dead_code)] // batch query API; not yet wired into main
+    pub fn query_batch(&self, queries: &[K], top_k: usize) -> Vec<Vec<QueryResult>> {
+        let qid = self.begin_query();
+
+        for seeder in &self.seeders {
+            let _ = seeder.tx.send(SeederCommand::QueryBatch {
+                query_id: qid,
+                queries: queries.to_vec(),
+                top_k,
+            });
+        }
+
+        // Merge each worker's per-query top-K into one list per query.
+        let mut merged: Vec<Vec<QueryResult>> = vec![Vec::new(); queries.len()];
+        for msg in self.gather(qid) {
+            if let SeederResult::BatchResult { results, .. } = msg {
+                for (dst, mut src) in merged.iter_mut().zip(results) {
+                    dst.append(&mut src);
+                }
+            }
+        }
+        self.end_query(qid);
+
+        for list in merged.iter_mut() {
+            list.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+            list.truncate(top_k);
+        }
+        merged
+    }
+
+    /// Run a resilient query, bounded by a deadline and a response quorum.
+    ///
+    /// Unlike `query`, this never blocks indefinitely on a stuck or panicked
+    /// worker: the gather returns once all workers answer, `min_responses`
+    /// answer, or the timeout elapses. The returned `QueryResponse` reports
+    /// which workers responded and which went missing, so the caller can see
+    /// when coverage was degraded. Late responses from missed workers are
+    /// discarded rather than blocking the next query.
+    #[allow(dead_code)] // resilient query API; not yet wired into main
+    pub fn query_with(&self, query_embedding: &K, top_k: usize, opts: QueryOptions) -> QueryResponse {
+        let qid = self.begin_query();
+
+        for seeder in &self.seeders {
+            let _ = seeder.tx.send(SeederCommand::Query {
+                query_id: qid,
+                query: query_embedding.clone(),
+                top_k,
+            });
+        }
+
+        let quorum = opts.min_responses.clamp(1, self.num_threads);
+        let deadline = Instant::now() + opts.timeout;
+        let (msgs, responded) = self.gather_with(qid, Some(deadline), quorum);
+        // Retire the qid now: any worker still outstanding will answer late,
+        // and the gather loop must discard — not park — those stragglers.
+        self.end_query(qid);
+
+        let mut all_results = Vec::new();
+        for msg in msgs {
+            if let SeederResult::QueryResult { mut results, .. } = msg {
+                all_results.append(&mut results);
+            }
+        }
+        all_results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+        all_results.truncate(top_k);
+
+        let mut responded: Vec<usize> = responded.into_iter().collect();
+        responded.sort_unstable();
+        let missing: Vec<usize> = (0..self.num_threads)
+            .filter(|w| responded.binary_search(w).is_err())
+            .collect();
+
+        QueryResponse { results: all_results, responded, missing }
+    }
+
+    /// Greedily partition `pieces` across `num_threads` shards to balance scan
+    /// cost (longest-processing-time first).
+    ///
+    /// Each piece's cost is the number of nonzero dimensions in its embedding —
+    /// a proxy for how much work `_dot` does on it, falling back to content
+    /// length for a piece with an empty embedding. Pieces are assigned heaviest
+    /// first, each to the currently least-loaded shard (tracked in a min-heap of
+    /// cumulative load), which keeps every worker's total scan cost close to the
+    /// average instead of letting an arbitrary contiguous slice dominate.
+    fn partition_weighted(pieces: Vec<Piece>, num_threads: usize) -> Vec<Vec<Piece>> {
+        let mut weighted: Vec<(usize, Piece)> = pieces
+            .into_iter()
+            .map(|p| (Self::piece_cost(&p), p))
+            .collect();
+        // Heaviest first — the defining move of LPT scheduling.
+        weighted.sort_by_key(|&(cost, _)| Reverse(cost));
+
+        // Min-heap of (cumulative_load, worker_id); Reverse flips the max-heap.
+        let mut loads: BinaryHeap<Reverse<(usize, usize)>> =
+            (0..num_threads).map(|w| Reverse((0, w))).collect();
+        let mut shards: Vec<Vec<Piece>> = vec![Vec::new(); num_threads];
+
+        for (cost, piece) in weighted {
+            let Reverse((load, w)) = loads.pop().expect("num_threads >= 1");
+            shards[w].push(piece);
+            loads.push(Reverse((load + cost, w)));
+        }
+
+        shards
+    }
+
+    /// Estimated per-piece scan cost: nonzero embedding dimensions, or content
+    /// length when the embedding carries no float weights.
+    fn piece_cost(piece: &Piece) -> usize {
+        let nonzero = piece.embedding.kf_data().iter().filter(|&&w| w != 0.0).count();
+        if nonzero > 0 {
+            nonzero
+        } else {
+            piece.content.len()
+        }
+    }
+
+    /// Collect exactly one response per worker for query `qid`, parking any
+    /// response belonging to a concurrent query in the staging map so its owner
+    /// still receives it. A worker id is accepted only once, so a stray
+    /// duplicate cannot inflate the gather.
+    fn gather(&self, qid: u64) -> Vec<SeederResult> {
+        self.gather_with(qid, None, self.num_threads).0
+    }
+
+    /// Gather responses for `qid`, stopping once `target` distinct workers have
+    /// answered, `deadline` passes (when `Some`), or the channel disconnects.
+    /// Returns the collected messages and the set of worker ids that answered.
+    fn gather_with(
+        &self,
+        qid: u64,
+        deadline: Option<Instant>,
+        target: usize,
+    ) -> (Vec<SeederResult>, std::collections::HashSet<usize>) {
+        let target = target.min(self.num_threads);
+        let mut collected = Vec::with_capacity(self.num_threads);
+        let mut seen: std::collections::HashSet<usize> = std::collections::HashSet::new();
+
+        // Hold the receiver for the whole gather: `mpsc::Receiver` is a
+        // single-consumer endpoint, so concurrent callers serialize here and
+        // drain it one at a time, parking each other's messages via `staging`.
+        // Only the receiver's holder parks messages, so taking it before the
+        // staging claim below means nothing can be parked for us afterwards.
+        let rx = self.result_rx.lock().unwrap();
+
+        // Claim anything already parked for our qid by a concurrent caller.
+        {
+            let mut staging = self.staging.lock().unwrap();
+            if let Some(parked) = staging.remove(&qid) {
+                for msg in parked {
+                    if seen.insert(msg.wid()) {
+                        collected.push(msg);
+                    }
+                }
+            }
+        }
+
+        while seen.len() < target {
+            let msg = match deadline {
+                Some(dl) => {
+                    let now = Instant::now();
+                    if now >= dl {
+                        break;
+                    }
+                    match rx.recv_timeout(dl - now) {
+                        Ok(msg) => msg,
+                        // Deadline hit or all senders gone — stop gathering.
+                        Err(RecvTimeoutError::Timeout) | Err(RecvTimeoutError::Disconnected) => break,
+                    }
+                }
+                None => match rx.recv() {
+                    Ok(msg) => msg,
+                    // All senders gone — nothing more will arrive.
+                    Err(_) => break,
+                },
+            };
+
+            if msg.qid() == qid {
+                if seen.insert(msg.wid()) {
+                    collected.push(msg);
+                }
+            } else if self.in_flight.lock().unwrap().contains(&msg.qid()) {
+                // Belongs to another live query — park it for its owner.
+                self.staging
+                    .lock()
+                    .unwrap()
+                    .entry(msg.qid())
+                    .or_default()
+                    .push(msg);
+            }
+            // Otherwise the owning query already returned (e.g. timed out);
+            // drop the straggler so `staging` can't leak dead-qid entries.
+        }
+
+        (collected, seen)
+    }
+
     /// How many seeders in the swarm?
     pub fn seeder_count(&self) -> usize {
         self.seeders.len()
@@ -189,4 +765,33 @@ impl Swarm {
     pub fn embedding_dim(&self) -> usize {
         self.vocab_size
     }
+
+    /// Tear the swarm down deterministically: broadcast `Shutdown`, then block
+    /// until every worker has exited its receive loop. Useful in tests and in
+    /// embedding scenarios that spin up many short-lived swarms.
+    #[allow(dead_code)] // explicit teardown helper; Drop covers the main path
+    pub fn join(mut self) {
+        self.shutdown();
+    }
+
+    /// Signal every worker to stop and join its thread. Idempotent — once the
+    /// handles are drained a second call (e.g. from `Drop` after `join`) is a
+    /// no-op.
+    fn shutdown(&mut self) {
+        if self.handles.is_empty() {
+            return;
+        }
+        for seeder in &self.seeders {
+            let _ = seeder.tx.send(SeederCommand::Shutdown);
+        }
+        for handle in self.handles.drain(..) {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for Swarm {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
 }